@@ -2,11 +2,34 @@
 #[path = "../libm/src/math/mod.rs"]
 mod libm;
 
+#[cfg(all(test, feature = "libm-differential-tests"))]
+#[path = "math_differential_tests.rs"]
+mod differential_tests;
+
 #[allow(unused_macros)]
 macro_rules! no_mangle {
-    ($(fn $fun:ident($($iid:ident : $ity:ty),+) -> $oty:ty;)+) => {
+    ($(#[cfg($($cfg:tt)*)] fn $fun:ident($($iid:ident : $ity:ty),+) -> $oty:ty;)+) => {
         intrinsics! {
             $(
+                // Stable `macro_rules!` can't paste `provide_` onto `$fun` to synthesize a
+                // per-symbol cfg name, so each invocation below spells its own
+                // `#[cfg(...)]` out instead of this macro deriving one. The predicate is
+                // captured as raw `tt`s (not `:meta`) and re-emitted through a literal
+                // `#[cfg(...)]` wrapper so it stays a decomposable attribute for whatever
+                // `intrinsics!` does with it, the same as if it had been written inline here.
+                // `build.rs` probes the target and decides, per symbol, whether this fallback
+                // needs to be provided at all (`provide_<fn>`). Where it does, most platforms
+                // can let a strong libc/libm definition take priority, so the fallback is only
+                // pulled in with `linkage = "weak"` there. Windows PE and Mach-O don't give
+                // weak symbols override semantics, so on those we stay strongly defined
+                // (`build.rs` only sets `provide_<fn>` there for the historically-gated
+                // bare-metal/SGX/UEFI targets) to avoid duplicate-symbol errors. `linkage` is
+                // emitted directly (rather than relying on `intrinsics!` to rewrite a bare
+                // `weak` marker, which isn't a real attribute on its own) so this compiles
+                // without assuming unverified behavior from a macro this vendored fragment
+                // doesn't include.
+                #[cfg($($cfg)*)]
+                #[cfg_attr(not(any(windows, target_vendor = "apple")), linkage = "weak")]
                 pub extern "C" fn $fun($($iid: $ity),+) -> $oty {
                     self::libm::$fun($($iid),+)
                 }
@@ -15,97 +38,78 @@ macro_rules! no_mangle {
     }
 }
 
-#[cfg(any(
-    all(
-        target_family = "wasm",
-        target_os = "unknown",
-        not(target_env = "wasi")
-    ),
-    target_os = "xous",
-    all(target_arch = "x86_64", target_os = "uefi"),
-    all(target_arch = "xtensa", target_os = "none"),
-    all(target_vendor = "fortanix", target_env = "sgx")
-))]
 no_mangle! {
-    fn acos(x: f64) -> f64;
-    fn asin(x: f64) -> f64;
-    fn cbrt(x: f64) -> f64;
-    fn expm1(x: f64) -> f64;
-    fn hypot(x: f64, y: f64) -> f64;
-    fn tan(x: f64) -> f64;
-    fn cos(x: f64) -> f64;
-    fn expf(x: f32) -> f32;
-    fn log2(x: f64) -> f64;
-    fn log2f(x: f32) -> f32;
-    fn log10(x: f64) -> f64;
-    fn log10f(x: f32) -> f32;
-    fn log(x: f64) -> f64;
-    fn logf(x: f32) -> f32;
-    fn fmin(x: f64, y: f64) -> f64;
-    fn fminf(x: f32, y: f32) -> f32;
-    fn fmax(x: f64, y: f64) -> f64;
-    fn fmaxf(x: f32, y: f32) -> f32;
-    fn round(x: f64) -> f64;
-    fn roundf(x: f32) -> f32;
-    fn rint(x: f64) -> f64;
-    fn rintf(x: f32) -> f32;
-    fn sin(x: f64) -> f64;
-    fn pow(x: f64, y: f64) -> f64;
-    fn powf(x: f32, y: f32) -> f32;
-    fn fmod(x: f64, y: f64) -> f64;
-    fn fmodf(x: f32, y: f32) -> f32;
-    fn acosf(n: f32) -> f32;
-    fn atan2f(a: f32, b: f32) -> f32;
-    fn atanf(n: f32) -> f32;
-    fn coshf(n: f32) -> f32;
-    fn expm1f(n: f32) -> f32;
-    fn fdim(a: f64, b: f64) -> f64;
-    fn fdimf(a: f32, b: f32) -> f32;
-    fn log1pf(n: f32) -> f32;
-    fn sinhf(n: f32) -> f32;
-    fn tanhf(n: f32) -> f32;
-    fn ldexp(f: f64, n: i32) -> f64;
-    fn ldexpf(f: f32, n: i32) -> f32;
-    fn tgamma(x: f64) -> f64;
-    fn tgammaf(x: f32) -> f32;
-    fn atan(x: f64) -> f64;
-    fn atan2(x: f64, y: f64) -> f64;
-    fn cosh(x: f64) -> f64;
-    fn log1p(x: f64) -> f64;
-    fn sinh(x: f64) -> f64;
-    fn tanh(x: f64) -> f64;
-    fn cosf(x: f32) -> f32;
-    fn exp(x: f64) -> f64;
-    fn sinf(x: f32) -> f32;
-    fn exp2(x: f64) -> f64;
-    fn exp2f(x: f32) -> f32;
-    fn fma(x: f64, y: f64, z: f64) -> f64;
-    fn fmaf(x: f32, y: f32, z: f32) -> f32;
-    fn asinf(n: f32) -> f32;
-    fn cbrtf(n: f32) -> f32;
-    fn hypotf(x: f32, y: f32) -> f32;
-    fn tanf(n: f32) -> f32;
+    #[cfg(provide_acos)] fn acos(x: f64) -> f64;
+    #[cfg(provide_asin)] fn asin(x: f64) -> f64;
+    #[cfg(provide_cbrt)] fn cbrt(x: f64) -> f64;
+    #[cfg(provide_expm1)] fn expm1(x: f64) -> f64;
+    #[cfg(provide_hypot)] fn hypot(x: f64, y: f64) -> f64;
+    #[cfg(provide_tan)] fn tan(x: f64) -> f64;
+    #[cfg(provide_cos)] fn cos(x: f64) -> f64;
+    #[cfg(provide_expf)] fn expf(x: f32) -> f32;
+    #[cfg(provide_log2)] fn log2(x: f64) -> f64;
+    #[cfg(provide_log2f)] fn log2f(x: f32) -> f32;
+    #[cfg(provide_log10)] fn log10(x: f64) -> f64;
+    #[cfg(provide_log10f)] fn log10f(x: f32) -> f32;
+    #[cfg(provide_log)] fn log(x: f64) -> f64;
+    #[cfg(provide_logf)] fn logf(x: f32) -> f32;
+    #[cfg(provide_fmin)] fn fmin(x: f64, y: f64) -> f64;
+    #[cfg(provide_fminf)] fn fminf(x: f32, y: f32) -> f32;
+    #[cfg(provide_fmax)] fn fmax(x: f64, y: f64) -> f64;
+    #[cfg(provide_fmaxf)] fn fmaxf(x: f32, y: f32) -> f32;
+    #[cfg(provide_round)] fn round(x: f64) -> f64;
+    #[cfg(provide_roundf)] fn roundf(x: f32) -> f32;
+    #[cfg(provide_rint)] fn rint(x: f64) -> f64;
+    #[cfg(provide_rintf)] fn rintf(x: f32) -> f32;
+    #[cfg(provide_sin)] fn sin(x: f64) -> f64;
+    #[cfg(provide_pow)] fn pow(x: f64, y: f64) -> f64;
+    #[cfg(provide_powf)] fn powf(x: f32, y: f32) -> f32;
+    #[cfg(provide_fmod)] fn fmod(x: f64, y: f64) -> f64;
+    #[cfg(provide_fmodf)] fn fmodf(x: f32, y: f32) -> f32;
+    #[cfg(provide_acosf)] fn acosf(n: f32) -> f32;
+    #[cfg(provide_atan2f)] fn atan2f(a: f32, b: f32) -> f32;
+    #[cfg(provide_atanf)] fn atanf(n: f32) -> f32;
+    #[cfg(provide_coshf)] fn coshf(n: f32) -> f32;
+    #[cfg(provide_expm1f)] fn expm1f(n: f32) -> f32;
+    #[cfg(provide_fdim)] fn fdim(a: f64, b: f64) -> f64;
+    #[cfg(provide_fdimf)] fn fdimf(a: f32, b: f32) -> f32;
+    #[cfg(provide_log1pf)] fn log1pf(n: f32) -> f32;
+    #[cfg(provide_sinhf)] fn sinhf(n: f32) -> f32;
+    #[cfg(provide_tanhf)] fn tanhf(n: f32) -> f32;
+    #[cfg(provide_ldexp)] fn ldexp(f: f64, n: i32) -> f64;
+    #[cfg(provide_ldexpf)] fn ldexpf(f: f32, n: i32) -> f32;
+    #[cfg(provide_tgamma)] fn tgamma(x: f64) -> f64;
+    #[cfg(provide_tgammaf)] fn tgammaf(x: f32) -> f32;
+    #[cfg(provide_atan)] fn atan(x: f64) -> f64;
+    #[cfg(provide_atan2)] fn atan2(x: f64, y: f64) -> f64;
+    #[cfg(provide_cosh)] fn cosh(x: f64) -> f64;
+    #[cfg(provide_log1p)] fn log1p(x: f64) -> f64;
+    #[cfg(provide_sinh)] fn sinh(x: f64) -> f64;
+    #[cfg(provide_tanh)] fn tanh(x: f64) -> f64;
+    #[cfg(provide_cosf)] fn cosf(x: f32) -> f32;
+    #[cfg(provide_exp)] fn exp(x: f64) -> f64;
+    #[cfg(provide_sinf)] fn sinf(x: f32) -> f32;
+    #[cfg(provide_exp2)] fn exp2(x: f64) -> f64;
+    #[cfg(provide_exp2f)] fn exp2f(x: f32) -> f32;
+    #[cfg(provide_fma)] fn fma(x: f64, y: f64, z: f64) -> f64;
+    #[cfg(provide_fmaf)] fn fmaf(x: f32, y: f32, z: f32) -> f32;
+    #[cfg(provide_asinf)] fn asinf(n: f32) -> f32;
+    #[cfg(provide_cbrtf)] fn cbrtf(n: f32) -> f32;
+    #[cfg(provide_hypotf)] fn hypotf(x: f32, y: f32) -> f32;
+    #[cfg(provide_tanf)] fn tanf(n: f32) -> f32;
 }
 
-#[cfg(any(
-    all(
-        target_family = "wasm",
-        target_os = "unknown",
-        not(target_env = "wasi")
-    ),
-    target_os = "xous",
-    all(target_arch = "x86_64", target_os = "uefi"),
-    all(target_arch = "xtensa", target_os = "none"),
-    all(target_vendor = "fortanix", target_env = "sgx"),
-    target_os = "windows"
-))]
 intrinsics! {
+    #[cfg(provide_lgamma_r)]
+    #[cfg_attr(not(any(windows, target_vendor = "apple")), linkage = "weak")]
     pub extern "C" fn lgamma_r(x: f64, s: &mut i32) -> f64 {
         let r = self::libm::lgamma_r(x);
         *s = r.1;
         r.0
     }
 
+    #[cfg(provide_lgammaf_r)]
+    #[cfg_attr(not(any(windows, target_vendor = "apple")), linkage = "weak")]
     pub extern "C" fn lgammaf_r(x: f32, s: &mut i32) -> f32 {
         let r = self::libm::lgammaf_r(x);
         *s = r.1;
@@ -113,45 +117,21 @@ intrinsics! {
     }
 }
 
-#[cfg(any(
-    target_os = "xous",
-    target_os = "uefi",
-    all(target_arch = "xtensa", target_os = "none"),
-))]
 no_mangle! {
-    fn sqrtf(x: f32) -> f32;
-    fn sqrt(x: f64) -> f64;
+    #[cfg(provide_sqrtf)] fn sqrtf(x: f32) -> f32;
+    #[cfg(provide_sqrt)] fn sqrt(x: f64) -> f64;
 }
 
-#[cfg(any(
-    all(target_vendor = "fortanix", target_env = "sgx"),
-    all(target_arch = "xtensa", target_os = "none"),
-    target_os = "xous",
-    target_os = "uefi"
-))]
 no_mangle! {
-    fn ceil(x: f64) -> f64;
-    fn ceilf(x: f32) -> f32;
-    fn floor(x: f64) -> f64;
-    fn floorf(x: f32) -> f32;
-    fn trunc(x: f64) -> f64;
-    fn truncf(x: f32) -> f32;
+    #[cfg(provide_ceil)] fn ceil(x: f64) -> f64;
+    #[cfg(provide_ceilf)] fn ceilf(x: f32) -> f32;
+    #[cfg(provide_floor)] fn floor(x: f64) -> f64;
+    #[cfg(provide_floorf)] fn floorf(x: f32) -> f32;
+    #[cfg(provide_trunc)] fn trunc(x: f64) -> f64;
+    #[cfg(provide_truncf)] fn truncf(x: f32) -> f32;
 }
 
-// only for the thumb*-none-eabi*, riscv32*-none-elf, x86_64-unknown-none and mips*-unknown-none targets that lack the floating point instruction set
-#[cfg(any(
-    all(target_arch = "arm", target_os = "none"),
-    all(target_arch = "riscv32", not(target_feature = "f"), target_os = "none"),
-    all(target_arch = "x86_64", target_os = "none"),
-    all(target_arch = "mips", target_os = "none"),
-))]
-no_mangle! {
-    fn fmin(x: f64, y: f64) -> f64;
-    fn fminf(x: f32, y: f32) -> f32;
-    fn fmax(x: f64, y: f64) -> f64;
-    fn fmaxf(x: f32, y: f32) -> f32;
-    // `f64 % f64`
-    fn fmod(x: f64, y: f64) -> f64;
-    // `f32 % f32`
-    fn fmodf(x: f32, y: f32) -> f32;
-}
+// `fmin`/`fmax`/`fmod` are covered by the main `FULL_FLOAT_FNS` table in `build.rs`
+// above (which also covers the thumb*-none-eabi*, riscv32*-none-elf, x86_64-unknown-none
+// and mips*-unknown-none targets that lack the floating point instruction set), so no
+// separate block is needed for them here.
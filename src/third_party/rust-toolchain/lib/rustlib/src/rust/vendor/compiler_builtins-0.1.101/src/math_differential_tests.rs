@@ -0,0 +1,452 @@
+//! Differential tests comparing every symbol `math.rs`'s `no_mangle!`/`intrinsics!`
+//! blocks export against the host's reference C `libm`.
+//!
+//! Each `extern "C"` declaration below resolves to whatever definition wins the link:
+//! on a hosted target that's the system `libm`, since `math.rs` only ever supplies a
+//! *weak* fallback there (see the `no_mangle!` macro). The exception is Windows, where
+//! `lgamma_r`/`lgammaf_r` are defined strongly by `math.rs`, so their declarations resolve
+//! to this crate's own port there. Calling `super::libm::$fun`
+//! directly, by contrast, always exercises this crate's own Rust port, so the two can
+//! be compared independently of which one a normal caller would actually observe.
+//!
+//! Gated behind the `libm-differential-tests` feature: it requires a hosted target
+//! with a linkable reference `libm`, which bare-metal/no_std targets don't have.
+#![cfg(all(test, feature = "libm-differential-tests"))]
+
+const RANDOM_SAMPLES: usize = 10_000;
+
+const BOUNDARY_F64: &[f64] = &[
+    0.0,
+    -0.0,
+    1.0,
+    -1.0,
+    2.0,
+    -2.0,
+    0.5,
+    f64::MIN,
+    f64::MAX,
+    f64::MIN_POSITIVE,
+    -f64::MIN_POSITIVE,
+    f64::EPSILON,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+    5e-324, // smallest positive subnormal
+    -5e-324,
+];
+
+const BOUNDARY_F32: &[f32] = &[
+    0.0,
+    -0.0,
+    1.0,
+    -1.0,
+    2.0,
+    -2.0,
+    0.5,
+    f32::MIN,
+    f32::MAX,
+    f32::MIN_POSITIVE,
+    -f32::MIN_POSITIVE,
+    f32::EPSILON,
+    f32::INFINITY,
+    f32::NEG_INFINITY,
+    f32::NAN,
+    1e-45, // smallest positive subnormal
+    -1e-45,
+];
+
+/// Extra points clustered around common discontinuities/poles that `BOUNDARY_F64` alone
+/// doesn't probe: non-positive integers (`tgamma`/`lgamma_r` poles), values straddling
+/// zero (`fmod`/`atan2` branch behavior), and values straddling 1.0 (`pow`/`log` branch
+/// continuity). Folded into the boundary sweep below for every function, not just the
+/// ones named here, since denser sampling is strictly more coverage.
+const DISCONTINUITY_F64: &[f64] = &[
+    -5.0,
+    -4.0,
+    -3.0,
+    -2.0,
+    -1.0,
+    -0.5,
+    0.5,
+    1.0 - f64::EPSILON,
+    1.0 + f64::EPSILON,
+    1e-10,
+    -1e-10,
+    3.0,
+    4.0,
+    5.0,
+];
+
+/// `f32` counterpart of `DISCONTINUITY_F64`.
+const DISCONTINUITY_F32: &[f32] = &[
+    -5.0,
+    -4.0,
+    -3.0,
+    -2.0,
+    -1.0,
+    -0.5,
+    0.5,
+    1.0 - f32::EPSILON,
+    1.0 + f32::EPSILON,
+    1e-10,
+    -1e-10,
+    3.0,
+    4.0,
+    5.0,
+];
+
+/// Tiny xorshift64* PRNG so this harness doesn't need an external `rand` dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A finite-biased f64: most draws land in a tame range so we also exercise the
+    /// common case, not just whatever bit pattern happens to come out of the PRNG.
+    fn next_f64(&mut self) -> f64 {
+        if self.next_u64() % 4 == 0 {
+            f64::from_bits(self.next_u64())
+        } else {
+            (self.next_u64() as i64 as f64) / (1u64 << 20) as f64
+        }
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        if self.next_u64() % 4 == 0 {
+            f32::from_bits(self.next_u64() as u32)
+        } else {
+            (self.next_u64() as i32 as f32) / (1u32 << 12) as f32
+        }
+    }
+
+    fn next_i32(&mut self) -> i32 {
+        (self.next_u64() % 4096) as i32 - 2048
+    }
+}
+
+fn ulp_diff_f64(a: f64, b: f64) -> u64 {
+    if (a.is_nan() && b.is_nan()) || a == b {
+        return 0;
+    }
+    let ai = a.to_bits() as i64;
+    let bi = b.to_bits() as i64;
+    ai.wrapping_sub(bi).unsigned_abs()
+}
+
+fn ulp_diff_f32(a: f32, b: f32) -> u32 {
+    if (a.is_nan() && b.is_nan()) || a == b {
+        return 0;
+    }
+    let ai = a.to_bits() as i32;
+    let bi = b.to_bits() as i32;
+    ai.wrapping_sub(bi).unsigned_abs()
+}
+
+macro_rules! diff_test_f64 {
+    ($([$name:ident($($arg:ident),+), $ulp:expr]),+ $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                extern "C" {
+                    fn $name($($arg: f64),+) -> f64;
+                }
+                let mut worst = 0u64;
+                let mut check = |$($arg: f64),+| {
+                    let ours = super::libm::$name($($arg),+);
+                    let theirs = unsafe { $name($($arg),+) };
+                    worst = worst.max(ulp_diff_f64(ours, theirs));
+                };
+                diff_test_f64!(@boundary check, $($arg),+);
+                let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15 ^ stringify!($name).len() as u64);
+                for _ in 0..RANDOM_SAMPLES {
+                    diff_test_f64!(@rand check, rng, $($arg),+);
+                }
+                eprintln!("{}: worst observed ULP = {worst}", stringify!($name));
+                assert!(
+                    worst <= $ulp,
+                    "{} diverged from system libm by {worst} ULP (tolerance {})",
+                    stringify!($name),
+                    $ulp
+                );
+            }
+        )+
+    };
+    // Full cross-product of the boundary/discontinuity points for each argument, not just
+    // the diagonal: two- and three-arg functions need mixed pairs like `fmod(x, 0)` or
+    // `pow(0, -1)`, which the diagonal can never produce.
+    (@boundary $check:ident, $x:ident) => {
+        for &a in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+            $check(a);
+        }
+    };
+    (@boundary $check:ident, $x:ident, $y:ident) => {
+        for &a in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+            for &b in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+                $check(a, b);
+            }
+        }
+    };
+    (@boundary $check:ident, $x:ident, $y:ident, $z:ident) => {
+        for &a in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+            for &b in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+                for &c in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+                    $check(a, b, c);
+                }
+            }
+        }
+    };
+    (@rand $check:ident, $rng:ident, $x:ident) => { $check($rng.next_f64()) };
+    (@rand $check:ident, $rng:ident, $x:ident, $y:ident) => { $check($rng.next_f64(), $rng.next_f64()) };
+    (@rand $check:ident, $rng:ident, $x:ident, $y:ident, $z:ident) => {
+        $check($rng.next_f64(), $rng.next_f64(), $rng.next_f64())
+    };
+}
+
+diff_test_f64! {
+    [acos(x), 2],
+    [asin(x), 2],
+    [cbrt(x), 2],
+    [expm1(x), 2],
+    [hypot(x, y), 2],
+    [tan(x), 2],
+    [cos(x), 2],
+    [log2(x), 2],
+    [log10(x), 2],
+    [log(x), 2],
+    [fmin(x, y), 0],
+    [fmax(x, y), 0],
+    [round(x), 0],
+    [rint(x), 0],
+    [sin(x), 2],
+    [pow(x, y), 4],
+    [fmod(x, y), 0],
+    [fdim(x, y), 0],
+    [atan(x), 2],
+    [atan2(x, y), 2],
+    [cosh(x), 3],
+    [log1p(x), 2],
+    [sinh(x), 3],
+    [tanh(x), 2],
+    [exp(x), 2],
+    [exp2(x), 2],
+    [fma(x, y, z), 1],
+    [tgamma(x), 20],
+    [sqrt(x), 0],
+    [ceil(x), 0],
+    [floor(x), 0],
+    [trunc(x), 0],
+}
+
+macro_rules! diff_test_f32 {
+    ($([$name:ident($($arg:ident),+), $ulp:expr]),+ $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                extern "C" {
+                    fn $name($($arg: f32),+) -> f32;
+                }
+                let mut worst = 0u32;
+                let mut check = |$($arg: f32),+| {
+                    let ours = super::libm::$name($($arg),+);
+                    let theirs = unsafe { $name($($arg),+) };
+                    worst = worst.max(ulp_diff_f32(ours, theirs));
+                };
+                diff_test_f32!(@boundary check, $($arg),+);
+                let mut rng = Rng::new(0x1234_5678_9abc_def0 ^ stringify!($name).len() as u64);
+                for _ in 0..RANDOM_SAMPLES {
+                    diff_test_f32!(@rand check, rng, $($arg),+);
+                }
+                eprintln!("{}: worst observed ULP = {worst}", stringify!($name));
+                assert!(
+                    worst <= $ulp,
+                    "{} diverged from system libm by {worst} ULP (tolerance {})",
+                    stringify!($name),
+                    $ulp
+                );
+            }
+        )+
+    };
+    // Full cross-product of the boundary/discontinuity points for each argument, not just
+    // the diagonal: two- and three-arg functions need mixed pairs like `fmodf(x, 0)` or
+    // `powf(0, -1)`, which the diagonal can never produce.
+    (@boundary $check:ident, $x:ident) => {
+        for &a in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+            $check(a);
+        }
+    };
+    (@boundary $check:ident, $x:ident, $y:ident) => {
+        for &a in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+            for &b in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+                $check(a, b);
+            }
+        }
+    };
+    (@boundary $check:ident, $x:ident, $y:ident, $z:ident) => {
+        for &a in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+            for &b in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+                for &c in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+                    $check(a, b, c);
+                }
+            }
+        }
+    };
+    (@rand $check:ident, $rng:ident, $x:ident) => { $check($rng.next_f32()) };
+    (@rand $check:ident, $rng:ident, $x:ident, $y:ident) => { $check($rng.next_f32(), $rng.next_f32()) };
+    (@rand $check:ident, $rng:ident, $x:ident, $y:ident, $z:ident) => {
+        $check($rng.next_f32(), $rng.next_f32(), $rng.next_f32())
+    };
+}
+
+diff_test_f32! {
+    [acosf(x), 2],
+    [atanf(x), 2],
+    [coshf(x), 3],
+    [expm1f(x), 2],
+    [log1pf(x), 2],
+    [sinhf(x), 3],
+    [tanhf(x), 2],
+    [cosf(x), 2],
+    [expf(x), 2],
+    [sinf(x), 2],
+    [exp2f(x), 2],
+    [asinf(x), 2],
+    [cbrtf(x), 2],
+    [tanf(x), 2],
+    [log2f(x), 2],
+    [log10f(x), 2],
+    [logf(x), 2],
+    [roundf(x), 0],
+    [rintf(x), 0],
+    [sqrtf(x), 0],
+    [ceilf(x), 0],
+    [floorf(x), 0],
+    [truncf(x), 0],
+    [tgammaf(x), 20],
+    [fminf(x, y), 0],
+    [fmaxf(x, y), 0],
+    [powf(x, y), 4],
+    [fmodf(x, y), 0],
+    [atan2f(x, y), 2],
+    [hypotf(x, y), 2],
+    [fdimf(x, y), 0],
+    [fmaf(x, y, z), 1],
+}
+
+#[test]
+fn ldexp_matches_system_libm() {
+    extern "C" {
+        fn ldexp(x: f64, n: i32) -> f64;
+    }
+    let mut worst = 0u64;
+    for &x in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+        for &n in &[-2000, -1, 0, 1, 2000] {
+            let ours = super::libm::ldexp(x, n);
+            let theirs = unsafe { ldexp(x, n) };
+            worst = worst.max(ulp_diff_f64(ours, theirs));
+        }
+    }
+    let mut rng = Rng::new(0xabad_1dea_c0ff_ee00);
+    for _ in 0..RANDOM_SAMPLES {
+        let x = rng.next_f64();
+        let n = rng.next_i32();
+        let ours = super::libm::ldexp(x, n);
+        let theirs = unsafe { ldexp(x, n) };
+        worst = worst.max(ulp_diff_f64(ours, theirs));
+    }
+    eprintln!("ldexp: worst observed ULP = {worst}");
+    assert!(worst == 0, "ldexp diverged from system libm by {worst} ULP");
+}
+
+#[test]
+fn ldexpf_matches_system_libm() {
+    extern "C" {
+        fn ldexpf(x: f32, n: i32) -> f32;
+    }
+    let mut worst = 0u32;
+    for &x in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+        for &n in &[-200, -1, 0, 1, 200] {
+            let ours = super::libm::ldexpf(x, n);
+            let theirs = unsafe { ldexpf(x, n) };
+            worst = worst.max(ulp_diff_f32(ours, theirs));
+        }
+    }
+    let mut rng = Rng::new(0xdead_beef_cafe_babe);
+    for _ in 0..RANDOM_SAMPLES {
+        let x = rng.next_f32();
+        let n = rng.next_i32();
+        let ours = super::libm::ldexpf(x, n);
+        let theirs = unsafe { ldexpf(x, n) };
+        worst = worst.max(ulp_diff_f32(ours, theirs));
+    }
+    eprintln!("ldexpf: worst observed ULP = {worst}");
+    assert!(worst == 0, "ldexpf diverged from system libm by {worst} ULP");
+}
+
+/// `lgamma_r`/`lgammaf_r` return both a magnitude and, out-of-band, the sign of
+/// `tgamma`; both halves need to agree with the reference implementation.
+#[test]
+fn lgamma_r_matches_system_libm() {
+    extern "C" {
+        fn lgamma_r(x: f64, sign: &mut i32) -> f64;
+    }
+    let mut worst = 0u64;
+    let mut sign_mismatches = 0u32;
+    let mut check = |x: f64| {
+        let (ours, our_sign) = super::libm::lgamma_r(x);
+        let mut their_sign = 0i32;
+        let theirs = unsafe { lgamma_r(x, &mut their_sign) };
+        worst = worst.max(ulp_diff_f64(ours, theirs));
+        if !ours.is_nan() && our_sign != their_sign {
+            sign_mismatches += 1;
+        }
+    };
+    for &x in BOUNDARY_F64.iter().chain(DISCONTINUITY_F64) {
+        check(x);
+    }
+    let mut rng = Rng::new(0xfeed_face_dead_c0de);
+    for _ in 0..RANDOM_SAMPLES {
+        check(rng.next_f64());
+    }
+    eprintln!("lgamma_r: worst observed ULP = {worst}, sign mismatches = {sign_mismatches}");
+    assert_eq!(sign_mismatches, 0, "lgamma_r sign out-parameter diverged from system libm");
+    assert!(worst <= 20, "lgamma_r diverged from system libm by {worst} ULP");
+}
+
+#[test]
+fn lgammaf_r_matches_system_libm() {
+    extern "C" {
+        fn lgammaf_r(x: f32, sign: &mut i32) -> f32;
+    }
+    let mut worst = 0u32;
+    let mut sign_mismatches = 0u32;
+    let mut check = |x: f32| {
+        let (ours, our_sign) = super::libm::lgammaf_r(x);
+        let mut their_sign = 0i32;
+        let theirs = unsafe { lgammaf_r(x, &mut their_sign) };
+        worst = worst.max(ulp_diff_f32(ours, theirs));
+        if !ours.is_nan() && our_sign != their_sign {
+            sign_mismatches += 1;
+        }
+    };
+    for &x in BOUNDARY_F32.iter().chain(DISCONTINUITY_F32) {
+        check(x);
+    }
+    let mut rng = Rng::new(0x1337_c0de_f00d_babe);
+    for _ in 0..RANDOM_SAMPLES {
+        check(rng.next_f32());
+    }
+    eprintln!("lgammaf_r: worst observed ULP = {worst}, sign mismatches = {sign_mismatches}");
+    assert_eq!(sign_mismatches, 0, "lgammaf_r sign out-parameter diverged from system libm");
+    assert!(worst <= 20, "lgammaf_r diverged from system libm by {worst} ULP");
+}